@@ -7,13 +7,17 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::BTreeMap;
 use std::fs::OpenOptions;
 use std::time::Duration;
 
 use anyhow::{anyhow, Context};
-use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, ResourceSpecifier, TopicReplication};
+use rdkafka::admin::{
+    AdminClient, AdminOptions, NewPartitions, NewTopic, ResourceSpecifier, TopicReplication,
+};
 use rdkafka::client::DefaultClientContext;
 use rdkafka::config::ClientConfig;
+use rdkafka::error::RDKafkaErrorCode;
 
 use dataflow_types::{
     AvroOcfSinkConnector, AvroOcfSinkConnectorBuilder, KafkaSinkConnector,
@@ -24,6 +28,179 @@ use ore::collections::CollectionExt;
 
 use crate::error::CoordError;
 
+/// A sampling of librdkafka global configuration property names, covering
+/// the properties operators are most likely to reach for (and typo) in a
+/// sink's `WITH` options.
+///
+/// This list is NOT exhaustive and must not be used to decide whether a
+/// property is valid — librdkafka's own registry is the source of truth for
+/// that, and we let `ClientConfig::create` make that call. This list exists
+/// only to power "did you mean" suggestions once librdkafka has already
+/// rejected a property.
+///
+/// See: https://github.com/edenhill/librdkafka/blob/master/CONFIGURATION.md
+const SAMPLE_LIBRDKAFKA_CONFIG_PROPERTIES: &[&str] = &[
+    "bootstrap.servers",
+    "client.id",
+    "message.max.bytes",
+    "message.copy.max.bytes",
+    "receive.message.max.bytes",
+    "max.in.flight.requests.per.connection",
+    "max.in.flight",
+    "metadata.request.timeout.ms",
+    "topic.metadata.refresh.interval.ms",
+    "metadata.max.age.ms",
+    "topic.metadata.refresh.fast.interval.ms",
+    "topic.metadata.refresh.sparse",
+    "topic.metadata.propagation.max.ms",
+    "topic.blacklist",
+    "debug",
+    "socket.timeout.ms",
+    "socket.blocking.max.ms",
+    "socket.send.buffer.bytes",
+    "socket.receive.buffer.bytes",
+    "socket.keepalive.enable",
+    "socket.nagle.disable",
+    "socket.max.fails",
+    "broker.address.ttl",
+    "broker.address.family",
+    "reconnect.backoff.ms",
+    "reconnect.backoff.max.ms",
+    "statistics.interval.ms",
+    "enabled_events",
+    "log_level",
+    "log.queue",
+    "log.thread.name",
+    "log.connection.close",
+    "client.rack",
+    "api.version.request",
+    "api.version.request.timeout.ms",
+    "api.version.fallback.ms",
+    "broker.version.fallback",
+    "security.protocol",
+    "ssl.cipher.suites",
+    "ssl.ca.location",
+    "ssl.certificate.location",
+    "ssl.key.location",
+    "ssl.key.password",
+    "ssl.endpoint.identification.algorithm",
+    "sasl.mechanism",
+    "sasl.mechanisms",
+    "sasl.username",
+    "sasl.password",
+    "sasl.kerberos.service.name",
+    "sasl.kerberos.principal",
+    "sasl.oauthbearer.config",
+    "group.id",
+    "session.timeout.ms",
+    "heartbeat.interval.ms",
+    "partition.assignment.strategy",
+    "enable.auto.commit",
+    "auto.commit.interval.ms",
+    "enable.auto.offset.store",
+    "queued.min.messages",
+    "queued.max.messages.kbytes",
+    "fetch.wait.max.ms",
+    "fetch.message.max.bytes",
+    "fetch.max.bytes",
+    "fetch.min.bytes",
+    "fetch.error.backoff.ms",
+    "auto.offset.reset",
+    "enable.partition.eof",
+    "queue.buffering.max.messages",
+    "queue.buffering.max.kbytes",
+    "queue.buffering.max.ms",
+    "linger.ms",
+    "message.send.max.retries",
+    "retries",
+    "retry.backoff.ms",
+    "queue.buffering.backpressure.threshold",
+    "compression.codec",
+    "compression.type",
+    "batch.num.messages",
+    "batch.size",
+    "delivery.report.only.error",
+    "delivery.timeout.ms",
+    "request.required.acks",
+    "acks",
+    "request.timeout.ms",
+    "message.timeout.ms",
+    "enable.idempotence",
+    "enable.gapless.guarantee",
+    "transactional.id",
+    "transaction.timeout.ms",
+    "partitioner",
+    "group.instance.id",
+    "socket.connection.setup.timeout.ms",
+    "ssl.key.pem",
+    "ssl.certificate.pem",
+    "ssl.ca.pem",
+    "sasl.oauthbearer.client.id",
+    "sasl.oauthbearer.client.secret",
+    "sasl.oauthbearer.token.endpoint.url",
+    "sasl.oauthbearer.scope",
+    "sasl.oauthbearer.extensions",
+    "sasl.oauthbearer.method",
+];
+
+/// Returns the shortest-edit-distance match for `key` in
+/// `SAMPLE_LIBRDKAFKA_CONFIG_PROPERTIES`, if one is close enough to plausibly
+/// be what the user meant to type.
+fn suggest_config_property(key: &str) -> Option<&'static str> {
+    SAMPLE_LIBRDKAFKA_CONFIG_PROPERTIES
+        .iter()
+        .map(|valid| (*valid, levenshtein_distance(key, valid)))
+        .filter(|(_, distance)| *distance <= 3)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(valid, _)| valid)
+}
+
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut prev_diag = row[0];
+        row[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let above = row[j];
+            row[j] = std::cmp::min(std::cmp::min(row[j - 1] + 1, row[j] + 1), prev_diag + cost);
+            prev_diag = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// librdkafka reports an unrecognized global configuration property as part
+/// of the client creation error message, in the form
+/// `No such configuration property: "the.bad.key"`. Pulls `the.bad.key` out
+/// of that message, if present, so we can offer a "did you mean" suggestion.
+fn unknown_config_property(message: &str) -> Option<&str> {
+    let prefix = "No such configuration property: \"";
+    let start = message.find(prefix)? + prefix.len();
+    let rest = &message[start..];
+    let end = rest.find('"')?;
+    Some(&rest[..end])
+}
+
+/// Turns a failure from `ClientConfig::create` into an `anyhow::Error`,
+/// enriching it with a "did you mean" suggestion when the failure names an
+/// unrecognized configuration property. librdkafka's own registry remains
+/// the source of truth for which properties are valid — we only decide
+/// whether to add a suggestion, never whether to reject the config.
+fn config_creation_error(e: rdkafka::error::KafkaError) -> anyhow::Error {
+    let message = e.to_string();
+    match unknown_config_property(&message).and_then(suggest_config_property) {
+        Some(suggestion) => anyhow!(
+            "error creating Kafka admin client for sink: {}; did you mean {:?}?",
+            message,
+            suggestion
+        ),
+        None => anyhow!("error creating Kafka admin client for sink: {}", message),
+    }
+}
+
 pub async fn build(
     builder: SinkConnectorBuilder,
     id: GlobalId,
@@ -39,9 +216,12 @@ async fn register_kafka_topic(
     topic: &str,
     mut partition_count: i32,
     mut replication_factor: i32,
+    topic_config: &BTreeMap<String, String>,
+    exists_ok: bool,
     ccsr: &ccsr::Client,
     value_schema: &str,
     key_schema: Option<&str>,
+    created_topics: &mut Vec<String>,
 ) -> Result<(Option<i32>, i32), CoordError> {
     // if either partition count or replication factor should be defaulted to the broker's config
     // (signaled by a value of -1), explicitly poll the broker to discover the defaults.
@@ -125,13 +305,18 @@ async fn register_kafka_topic(
         }
     }
 
+    let mut new_topic = NewTopic::new(
+        &topic,
+        partition_count,
+        TopicReplication::Fixed(replication_factor),
+    );
+    for (key, value) in topic_config {
+        new_topic = new_topic.set(key, value);
+    }
+
     let res = client
         .create_topics(
-            &[NewTopic::new(
-                &topic,
-                partition_count,
-                TopicReplication::Fixed(replication_factor),
-            )],
+            &[new_topic],
             &AdminOptions::new().request_timeout(Some(Duration::from_secs(5))),
         )
         .await
@@ -144,13 +329,28 @@ async fn register_kafka_topic(
             res.len()
         );
     }
-    res.into_element()
-        .map_err(|(_, e)| anyhow!("error creating topic {} for sink: {}", topic, e))?;
+    match res.into_element() {
+        Ok(_) => {
+            // From this point on, the topic exists on the broker, so any
+            // error in the rest of the build path must roll it back to avoid
+            // leaving an orphaned topic behind for a retry to collide with.
+            created_topics.push(topic.to_string());
+        }
+        Err((_, RDKafkaErrorCode::TopicAlreadyExists)) if exists_ok => {
+            reconcile_existing_topic(
+                client,
+                topic,
+                partition_count,
+                replication_factor,
+                topic_config,
+            )
+            .await
+            .context("error reusing existing topic for sink")?;
+        }
+        Err((_, e)) => coord_bail!("error creating topic {} for sink: {}", topic, e),
+    }
 
     // Publish value schema for the topic.
-    //
-    // TODO(benesch): do we need to delete the Kafka topic if publishing the
-    // schema fails?
     let value_schema_id = ccsr
         .publish_schema(&format!("{}-value", topic), value_schema)
         .await
@@ -169,6 +369,167 @@ async fn register_kafka_topic(
     Ok((key_schema_id, value_schema_id))
 }
 
+/// Reconciles an existing topic with the partition count, replication
+/// factor, and topic-level config that were requested for it, so that
+/// reusing the topic (rather than failing with `TopicAlreadyExists`) is
+/// observably equivalent to having created it fresh.
+///
+/// A partition count larger than the existing topic's is accommodated by
+/// growing the topic via `create_partitions`, since Kafka cannot shrink
+/// partitions, a smaller requested partition count is an error, as is any
+/// mismatch in replication factor or topic-level config.
+async fn reconcile_existing_topic(
+    client: &AdminClient<DefaultClientContext>,
+    topic: &str,
+    partition_count: i32,
+    replication_factor: i32,
+    topic_config: &BTreeMap<String, String>,
+) -> Result<(), CoordError> {
+    let metadata = client
+        .inner()
+        .fetch_metadata(Some(topic), Duration::from_secs(5))
+        .with_context(|| format!("error fetching metadata for existing topic {}", topic))?;
+    let meta_topic = metadata
+        .topics()
+        .iter()
+        .find(|t| t.name() == topic)
+        .ok_or_else(|| anyhow!("topic {} disappeared while verifying existing topic", topic))?;
+
+    let actual_partition_count = meta_topic.partitions().len() as i32;
+    let actual_replication_factor = meta_topic
+        .partitions()
+        .get(0)
+        .map_or(0, |p| p.replicas().len() as i32);
+
+    let configs = client
+        .describe_configs(
+            &[ResourceSpecifier::Topic(topic)],
+            &AdminOptions::new().request_timeout(Some(Duration::from_secs(5))),
+        )
+        .await
+        .with_context(|| format!("error fetching configuration for existing topic {}", topic))?;
+    if configs.len() != 1 {
+        coord_bail!(
+            "error verifying existing topic {} for sink: broker returned {} config results, but one was expected",
+            topic,
+            configs.len()
+        );
+    }
+    let config = configs.into_element().map_err(|e| {
+        anyhow!(
+            "error reading configuration for existing topic {}: {}",
+            topic,
+            e
+        )
+    })?;
+
+    let mut mismatches = vec![];
+    if actual_replication_factor != replication_factor {
+        mismatches.push(format!(
+            "replication factor (existing: {}, requested: {})",
+            actual_replication_factor, replication_factor
+        ));
+    }
+    for (key, expected) in topic_config {
+        let actual = config
+            .entries
+            .iter()
+            .find(|e| &e.name == key)
+            .and_then(|e| e.value.as_deref());
+        if actual != Some(expected.as_str()) {
+            mismatches.push(format!(
+                "config {} (existing: {:?}, requested: {})",
+                key, actual, expected
+            ));
+        }
+    }
+    if actual_partition_count > partition_count {
+        mismatches.push(format!(
+            "partition count (existing: {}, requested: {}): Kafka cannot shrink partitions",
+            actual_partition_count, partition_count
+        ));
+    }
+
+    if !mismatches.is_empty() {
+        coord_bail!(
+            "existing topic {} does not match requested configuration: {}",
+            topic,
+            mismatches.join(", ")
+        );
+    }
+
+    if actual_partition_count < partition_count {
+        let res = client
+            .create_partitions(
+                &[NewPartitions::new(topic, partition_count as usize)],
+                &AdminOptions::new().request_timeout(Some(Duration::from_secs(5))),
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "error increasing partition count for existing topic {} for sink",
+                    topic
+                )
+            })?;
+        if res.len() != 1 {
+            coord_bail!(
+                "error increasing partition count for existing topic {} for sink: \
+                 kafka returned {} results, but exactly one result was expected",
+                topic,
+                res.len()
+            );
+        }
+        res.into_element().map_err(|(_, e)| {
+            anyhow!(
+                "error increasing partition count for existing topic {} for sink: {}",
+                topic,
+                e
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Deletes `topics` from the broker, best-effort, to undo a partially
+/// completed sink build. A topic that is already gone (e.g. because it was
+/// never successfully created) is treated as a successful deletion so that
+/// rollback is idempotent. Errors deleting a topic are logged but otherwise
+/// swallowed, since they must not mask the original error that triggered the
+/// rollback.
+async fn rollback_kafka_topics(client: &AdminClient<DefaultClientContext>, topics: &[String]) {
+    if topics.is_empty() {
+        return;
+    }
+    let topic_refs: Vec<_> = topics.iter().map(String::as_str).collect();
+    let res = client
+        .delete_topics(
+            &topic_refs,
+            &AdminOptions::new().request_timeout(Some(Duration::from_secs(5))),
+        )
+        .await;
+    match res {
+        Ok(results) => {
+            for result in results {
+                match result {
+                    Ok(_) => (),
+                    Err((_, RDKafkaErrorCode::UnknownTopicOrPartition)) => (),
+                    Err((topic, e)) => log::warn!(
+                        "error rolling back kafka topic {} after failed sink creation: {}",
+                        topic,
+                        e
+                    ),
+                }
+            }
+        }
+        Err(e) => log::warn!(
+            "error issuing delete_topics request to roll back topics {} after failed sink creation: {}",
+            topics.join(", "),
+            e
+        ),
+    }
+}
+
 async fn build_kafka(
     builder: KafkaSinkConnectorBuilder,
     id: GlobalId,
@@ -181,36 +542,71 @@ async fn build_kafka(
     for (k, v) in builder.config_options.iter() {
         config.set(k, v);
     }
-    let client = config
-        .create::<AdminClient<_>>()
-        .expect("creating admin client failed");
+    let client: AdminClient<_> = config.create().map_err(config_creation_error)?;
     let ccsr = builder.ccsr_config.build();
 
-    let (key_schema_id, value_schema_id) = register_kafka_topic(
+    // Track every topic we successfully create so that, if a later step in
+    // the build path fails, we can roll all of them back rather than leaving
+    // an orphaned topic for a retry of this sink to collide with.
+    let mut created_topics = Vec::new();
+
+    let (key_schema_id, value_schema_id) = match register_kafka_topic(
         &client,
         &topic,
         builder.partition_count,
         builder.replication_factor,
+        &builder.topic_config,
+        builder.exists_ok,
         &ccsr,
         &builder.value_schema,
         builder.key_schema.as_deref(),
+        &mut created_topics,
     )
     .await
-    .context("error registering kafka topic for sink")?;
+    .context("error registering kafka topic for sink")
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            rollback_kafka_topics(&client, &created_topics).await;
+            return Err(e.into());
+        }
+    };
 
     let consistency = if let Some(consistency_value_schema) = builder.consistency_value_schema {
         let consistency_topic = format!("{}-consistency", topic);
-        let (_, consistency_schema_id) = register_kafka_topic(
+
+        // The consistency topic holds the sink's exactly-once progress
+        // markers, which must survive indefinitely, so compact it forever
+        // unless the user has explicitly overridden these settings.
+        let mut consistency_topic_config = builder.consistency_topic_config;
+        consistency_topic_config
+            .entry("cleanup.policy".into())
+            .or_insert_with(|| "compact".into());
+        consistency_topic_config
+            .entry("retention.ms".into())
+            .or_insert_with(|| "-1".into());
+
+        let consistency_schema_id = match register_kafka_topic(
             &client,
             &consistency_topic,
             1,
             builder.replication_factor,
+            &consistency_topic_config,
+            builder.exists_ok,
             &ccsr,
             &consistency_value_schema,
             None,
+            &mut created_topics,
         )
         .await
-        .context("error registering kafka consistency topic for sink")?;
+        .context("error registering kafka consistency topic for sink")
+        {
+            Ok((_, schema_id)) => schema_id,
+            Err(e) => {
+                rollback_kafka_topics(&client, &created_topics).await;
+                return Err(e.into());
+            }
+        };
 
         Some(KafkaSinkConsistencyConnector {
             topic: consistency_topic,